@@ -0,0 +1,1759 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::collections::VecDeque;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use futures::task::AtomicWaker;
+use futures::{Sink, Stream};
+
+use crossbeam_epoch as epoch;
+use crossbeam_epoch::{Atomic, Owned, Shared};
+
+/// A single ring-buffer slot. `seq` tracks which "lap" around the ring the
+/// slot is currently valid for, so producers and consumers can tell whether
+/// a slot is ready for them without ever reasoning about `head`/`tail`
+/// directly racing the data write.
+struct Cell<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Pads `T` out to its own cache line (128 bytes, matching the "adjacent
+/// line prefetch" effective line size some x86 parts exhibit) so two padded
+/// fields sitting next to each other in a struct never share a line. Without
+/// this, a producer's store to one counter and a consumer's store to the
+/// other ping-pong the same cache line between cores even though neither
+/// side ever reads the other's field directly.
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+impl<T> std::ops::Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// A mutex-guarded list of parked threads waiting on a condition (queue
+/// non-full or non-empty). `count` lets the fast path (nobody waiting) skip
+/// the mutex entirely, so `push_back`/`pop_front` stay lock-free when no
+/// blocking caller is involved.
+struct WaitList {
+    count: AtomicUsize,
+    threads: Mutex<VecDeque<Thread>>,
+}
+
+impl WaitList {
+    fn new() -> Self {
+        WaitList {
+            count: AtomicUsize::new(0),
+            threads: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Register the calling thread as waiting, returning a guard that
+    /// removes the registration again on drop. Callers register once per
+    /// failed attempt and let the guard go out of scope at the end of that
+    /// loop iteration (whether it parks, recheck passes, or it returns)
+    /// instead of registering and never cleaning up, which would otherwise
+    /// grow this list by one stale `Thread` per call forever.
+    fn register(&self) -> Registration<'_> {
+        let mut threads = self.threads.lock().unwrap();
+        threads.push_back(thread::current());
+        self.count.store(threads.len(), Ordering::SeqCst);
+        Registration { list: self }
+    }
+
+    fn deregister_current(&self) {
+        let id = thread::current().id();
+        let mut threads = self.threads.lock().unwrap();
+        if let Some(pos) = threads.iter().position(|t| t.id() == id) {
+            threads.remove(pos);
+            self.count.store(threads.len(), Ordering::SeqCst);
+        }
+    }
+
+    fn notify_one(&self) {
+        if self.count.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let mut threads = self.threads.lock().unwrap();
+        if let Some(thread) = threads.pop_front() {
+            self.count.store(threads.len(), Ordering::SeqCst);
+            thread.unpark();
+        }
+    }
+}
+
+/// Guard returned by [`WaitList::register`]; removes the calling thread's
+/// registration when dropped.
+struct Registration<'a> {
+    list: &'a WaitList,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.list.deregister_current();
+    }
+}
+
+/// Bounded MPMC queue using Dmitry Vyukov's per-slot sequence algorithm.
+///
+/// Unlike a naive "CAS the index, then write the slot" scheme, every slot
+/// carries its own sequence counter. A producer/consumer only commits to a
+/// slot once it has confirmed (via `seq`) that the slot is actually ready
+/// for it, so there is no window where a claimed-but-not-yet-written slot
+/// can be observed by the other side.
+///
+/// `enqueue_pos` and `dequeue_pos` are each pinned to their own cache line
+/// via [`CachePadded`]: under contention they're written by different
+/// cores (producers and consumers respectively) far more often than either
+/// side reads the other, so keeping them apart avoids false-sharing
+/// ping-pong. There's no separate "cached copy of the opposite counter"
+/// field here the way a classic head/tail SPSC ring needs one — each
+/// per-slot `Cell::seq` already plays that role, since `push_back`/
+/// `pop_front` only ever consult their own position counter and the slot
+/// they're about to touch.
+pub struct LockFreeDeque<T> {
+    buffer: Vec<Cell<T>>,
+    capacity: usize,
+    enqueue_pos: CachePadded<AtomicUsize>,
+    dequeue_pos: CachePadded<AtomicUsize>,
+    send_waiters: WaitList,
+    recv_waiters: WaitList,
+}
+
+unsafe impl<T: Send> Send for LockFreeDeque<T> {}
+unsafe impl<T: Send> Sync for LockFreeDeque<T> {}
+
+impl<T> LockFreeDeque<T> {
+    pub fn with_capacity(capacity: usize) -> Arc<Self> {
+        // A single-slot ring can't distinguish "just written, not yet read"
+        // from "read and ready for the next write" (both states land on the
+        // same sequence number), so the algorithm needs at least 2 slots to
+        // give correct backpressure. This used to be `capacity > 0`; a
+        // capacity-1 ring let a second concurrent push_back silently
+        // overwrite an unread slot, and would make send_blocking/recv_timeout
+        // (added alongside this fix) spin forever waiting for a slot that
+        // could never become ready. See
+        // `test_concurrent_push_pop_at_minimum_capacity` for the regression
+        // this guards against, and `test_capacity_below_minimum_is_rejected`
+        // below for the guard itself.
+        assert!(capacity >= 2, "capacity must be at least 2");
+        let buffer = (0..capacity)
+            .map(|i| Cell {
+                seq: AtomicUsize::new(i),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Arc::new(Self {
+            buffer,
+            capacity,
+            enqueue_pos: CachePadded(AtomicUsize::new(0)),
+            dequeue_pos: CachePadded(AtomicUsize::new(0)),
+            send_waiters: WaitList::new(),
+            recv_waiters: WaitList::new(),
+        })
+    }
+
+    pub fn push_back(&self, value: T) -> Result<(), T> {
+        let mut pos = self.enqueue_pos.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.buffer[pos % self.capacity];
+            let seq = cell.seq.load(Ordering::Acquire);
+            let diff = seq as isize - pos as isize;
+
+            if diff == 0 {
+                // This slot has caught up to us: it's free to write into.
+                if self
+                    .enqueue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    unsafe {
+                        (*cell.data.get()).write(value);
+                    }
+                    cell.seq.store(pos + 1, Ordering::Release);
+                    self.recv_waiters.notify_one();
+                    return Ok(());
+                }
+                // Someone else claimed this position first, reload and retry.
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                // The slot is still occupied by an item that hasn't been
+                // dequeued yet: the queue is full.
+                return Err(value);
+            } else {
+                // Another producer is ahead of us, catch up.
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let mut pos = self.dequeue_pos.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.buffer[pos % self.capacity];
+            let seq = cell.seq.load(Ordering::Acquire);
+            let diff = seq as isize - (pos + 1) as isize;
+
+            if diff == 0 {
+                // A value has been published into this slot: take it.
+                if self
+                    .dequeue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let value = unsafe { (*cell.data.get()).assume_init_read() };
+                    // Hand the slot back to the producers one full lap later.
+                    cell.seq.store(pos + self.capacity, Ordering::Release);
+                    self.send_waiters.notify_one();
+                    return Some(value);
+                }
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                // No producer has published into this slot yet: empty.
+                return None;
+            } else {
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let dequeue_pos = self.dequeue_pos.load(Ordering::Relaxed);
+        let enqueue_pos = self.enqueue_pos.load(Ordering::Relaxed);
+        enqueue_pos.saturating_sub(dequeue_pos)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity
+    }
+
+    /// Push a value, parking the calling thread while the ring is full
+    /// instead of busy-spinning. Unparked by a successful `pop_front`.
+    pub fn send_blocking(&self, value: T) {
+        let mut value = value;
+        loop {
+            match self.push_back(value) {
+                Ok(()) => return,
+                Err(v) => {
+                    value = v;
+                    let _registration = self.send_waiters.register();
+                    // A pop_front may have raced us between the failed push
+                    // and registering; recheck before parking so we don't
+                    // miss it. Either way, `_registration` is deregistered
+                    // when it drops at the end of this iteration.
+                    if !self.is_full() {
+                        continue;
+                    }
+                    thread::park();
+                }
+            }
+        }
+    }
+
+    /// Pop a value, parking the calling thread while the ring is empty
+    /// instead of busy-spinning. Unparked by a successful `push_back`.
+    pub fn recv_blocking(&self) -> T {
+        loop {
+            if let Some(value) = self.pop_front() {
+                return value;
+            }
+            // Dropped at the end of this iteration either way, so a thread
+            // that proceeds without parking doesn't leave a stale entry
+            // behind.
+            let _registration = self.recv_waiters.register();
+            if !self.is_empty() {
+                continue;
+            }
+            thread::park();
+        }
+    }
+
+    /// Like `recv_blocking`, but gives up and returns `None` once `timeout`
+    /// has elapsed without a value becoming available.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(value) = self.pop_front() {
+                return Some(value);
+            }
+            let _registration = self.recv_waiters.register();
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            if !self.is_empty() {
+                continue;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+/// Block until one of `queues` has an item ready, then pop and return it
+/// along with the index of the queue it came from. Equivalent to registering
+/// interest across all of them and waking on whichever becomes ready first.
+pub fn select<T>(queues: &[&LockFreeDeque<T>]) -> (usize, T) {
+    loop {
+        for (index, queue) in queues.iter().enumerate() {
+            if let Some(value) = queue.pop_front() {
+                return (index, value);
+            }
+        }
+        // One registration per queue, all dropped (and so deregistered) at
+        // the end of this iteration whether we continue or park, so a
+        // mostly-idle queue in `queues` doesn't accumulate a stale `Thread`
+        // per call the way registering-and-forgetting would.
+        let _registrations: Vec<_> = queues.iter().map(|q| q.recv_waiters.register()).collect();
+        if queues.iter().any(|queue| !queue.is_empty()) {
+            continue;
+        }
+        thread::park();
+    }
+}
+
+impl<T> Drop for LockFreeDeque<T> {
+    fn drop(&mut self) {
+        // Any slot between dequeue_pos and enqueue_pos still holds a value
+        // that was written but never taken; drop it in place so we don't
+        // leak. `&mut self` means nobody else can be touching the queue.
+        let mut pos = *self.dequeue_pos.get_mut();
+        let end = *self.enqueue_pos.get_mut();
+        while pos != end {
+            let cell = &mut self.buffer[pos % self.capacity];
+            unsafe {
+                cell.data.get_mut().assume_init_drop();
+            }
+            pos = pos.wrapping_add(1);
+        }
+    }
+}
+
+/// Common interface over the bounded [`LockFreeDeque`] ring and the
+/// unbounded [`UnboundedQueue`] linked list, so callers can pick their
+/// backpressure story (bounded, `push` can fail) versus unbounded growth
+/// (`push` always succeeds) behind the same API.
+pub trait Queue<T> {
+    fn push(&self, value: T) -> Result<(), T>;
+    fn pop(&self) -> Option<T>;
+}
+
+impl<T> Queue<T> for LockFreeDeque<T> {
+    fn push(&self, value: T) -> Result<(), T> {
+        self.push_back(value)
+    }
+
+    fn pop(&self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+/// A node in the Michael-Scott queue. The head is always a dummy/sentinel
+/// node whose `value` is never read; every other node holds exactly one
+/// live, not-yet-popped value in `value` until it is dequeued (or the queue
+/// is dropped).
+struct MsNode<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    next: Atomic<MsNode<T>>,
+}
+
+impl<T> MsNode<T> {
+    fn sentinel() -> Self {
+        MsNode {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            next: Atomic::null(),
+        }
+    }
+
+    fn new(value: T) -> Self {
+        MsNode {
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+            next: Atomic::null(),
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for MsNode<T> {}
+unsafe impl<T: Send> Sync for MsNode<T> {}
+
+/// Unbounded MPMC queue backed by a Michael & Scott lock-free linked list.
+/// Unlike [`LockFreeDeque`], `push_back` never fails for lack of room: it
+/// always allocates a new node. Nodes that fall off the head are reclaimed
+/// via `crossbeam_epoch` (each operation pins a guard for its duration), so
+/// a thread that's still dereferencing a node another thread has just
+/// unlinked never sees it freed out from under it, and no node is ever
+/// freed twice.
+pub struct UnboundedQueue<T> {
+    head: CachePadded<Atomic<MsNode<T>>>,
+    tail: CachePadded<Atomic<MsNode<T>>>,
+}
+
+unsafe impl<T: Send> Send for UnboundedQueue<T> {}
+unsafe impl<T: Send> Sync for UnboundedQueue<T> {}
+
+impl<T> UnboundedQueue<T> {
+    pub fn new() -> Arc<Self> {
+        let guard = epoch::pin();
+        let sentinel = Owned::new(MsNode::sentinel()).into_shared(&guard);
+        Arc::new(Self {
+            head: CachePadded(Atomic::from(sentinel)),
+            tail: CachePadded(Atomic::from(sentinel)),
+        })
+    }
+
+    /// Always succeeds: the queue grows to fit whatever is pushed.
+    pub fn push_back(&self, value: T) {
+        let guard = epoch::pin();
+        let new_node = Owned::new(MsNode::new(value)).into_shared(&guard);
+        loop {
+            let tail = self.tail.load(Ordering::Acquire, &guard);
+            let tail_ref = unsafe { tail.deref() };
+            let next = tail_ref.next.load(Ordering::Acquire, &guard);
+
+            if next.is_null() {
+                // Tail looks up to date: try to link the new node after it.
+                if tail_ref
+                    .next
+                    .compare_exchange(
+                        Shared::null(),
+                        new_node,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                        &guard,
+                    )
+                    .is_ok()
+                {
+                    // Best-effort: swing tail forward. If this CAS loses,
+                    // whoever's ahead of us (or a later pusher) will do it.
+                    let _ = self.tail.compare_exchange(
+                        tail,
+                        new_node,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                        &guard,
+                    );
+                    return;
+                }
+            } else {
+                // Tail has fallen behind (a concurrent pusher linked a node
+                // but hasn't swung tail yet): help it along and retry.
+                let _ = self.tail.compare_exchange(
+                    tail,
+                    next,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                    &guard,
+                );
+            }
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire, &guard);
+            let tail = self.tail.load(Ordering::Acquire, &guard);
+            let head_ref = unsafe { head.deref() };
+            let next = head_ref.next.load(Ordering::Acquire, &guard);
+
+            if head == tail {
+                if next.is_null() {
+                    // Empty.
+                    return None;
+                }
+                // Tail has fallen behind; help it along and retry.
+                let _ = self.tail.compare_exchange(
+                    tail,
+                    next,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                    &guard,
+                );
+                continue;
+            }
+
+            // Safety: `next` is non-null here because `head != tail` implies
+            // there's at least one real node after the sentinel.
+            let next_ref = unsafe { next.deref() };
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::Release, Ordering::Relaxed, &guard)
+                .is_ok()
+            {
+                // We won: `next` is the new sentinel, and we're the only
+                // caller entitled to read its value out.
+                let value = unsafe { (*next_ref.value.get()).assume_init_read() };
+                unsafe {
+                    guard.defer_destroy(head);
+                }
+                return Some(value);
+            }
+            // Lost the race to another popper; retry without having read
+            // anything, so there's nothing to undo.
+        }
+    }
+}
+
+impl<T> Queue<T> for UnboundedQueue<T> {
+    fn push(&self, value: T) -> Result<(), T> {
+        self.push_back(value);
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+impl<T> Drop for UnboundedQueue<T> {
+    fn drop(&mut self) {
+        // `&mut self` guarantees no concurrent access, so an unprotected
+        // guard is sound here and avoids pinning an epoch just to tear
+        // down.
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut current = self.head.load(Ordering::Relaxed, guard);
+            let mut is_sentinel = true;
+            while !current.is_null() {
+                let mut owned = current.into_owned();
+                let next = owned.next.load(Ordering::Relaxed, guard);
+                if !is_sentinel {
+                    // Every node but the original sentinel holds a pending
+                    // value that was never popped; drop it before freeing
+                    // the node itself.
+                    owned.value.get_mut().assume_init_drop();
+                }
+                is_sentinel = false;
+                drop(owned);
+                current = next;
+            }
+        }
+    }
+}
+
+/// Shared state behind a work-stealing deque: one owner end (`tail`) that is
+/// only ever touched by the thread that created it, and one steal end
+/// (`head`) that any number of thief threads may contend on with a CAS.
+///
+/// Unlike `LockFreeDeque`, `tail` here is never raced on by multiple
+/// writers, so the owner's `push_back`/`pop_back` need no CAS at all except
+/// for the single-element race against stealers described in `pop_back`.
+///
+/// `head` and `tail` are cache-line padded for the same reason as
+/// `LockFreeDeque`'s counters: the owner writes `tail` and thieves write
+/// `head` from other cores, and keeping them apart avoids false sharing.
+///
+/// Unlike the Vyukov ring (whose per-slot `seq` already tells each side
+/// everything it needs without ever reading the other's counter), a plain
+/// head/tail deque like this one *does* need to read the opposite counter
+/// on the hot path: `push_back` must know whether `head` has fallen a full
+/// `capacity` behind `tail` (the ring is full) before writing. Co-locating a `cached_head` with `tail` on the
+/// owner's cache line lets `push_back` consult that cache first and only
+/// pay for a cross-line read of the real, padded-away `head` when the
+/// cache suggests the ring might actually be full.
+struct TailCounter {
+    tail: AtomicUsize,
+    cached_head: UnsafeCell<usize>,
+}
+
+struct WorkStealingInner<T> {
+    buffer: Vec<UnsafeCell<MaybeUninit<T>>>,
+    capacity: usize,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<TailCounter>,
+}
+
+unsafe impl<T: Send> Send for WorkStealingInner<T> {}
+unsafe impl<T: Send> Sync for WorkStealingInner<T> {}
+
+impl<T> Drop for WorkStealingInner<T> {
+    fn drop(&mut self) {
+        let mut pos = *self.head.get_mut();
+        let end = *self.tail.tail.get_mut();
+        while pos != end {
+            unsafe {
+                self.buffer[pos % self.capacity].get_mut().assume_init_drop();
+            }
+            pos = pos.wrapping_add(1);
+        }
+    }
+}
+
+/// The single handle that may `push_back`/`pop_back` a work-stealing deque.
+/// There is exactly one `Owner` per deque; clone a `Stealer` to let other
+/// threads take work from the other end.
+///
+/// `push_back`/`pop_back` take `&self` with no synchronization against a
+/// second concurrent caller on the same end, so that invariant has to be
+/// enforced at compile time rather than just documented: the `PhantomData`
+/// marker makes `Owner` `!Sync`, so it can only ever be used from the one
+/// thread holding it (the same trick `crossbeam_deque::Worker` uses).
+pub struct Owner<T> {
+    inner: Arc<WorkStealingInner<T>>,
+    _not_sync: PhantomData<std::cell::Cell<()>>,
+}
+
+/// A cloneable handle that lets any thread steal from the head of a
+/// work-stealing deque. Stealing never blocks the owner's `push_back`/
+/// `pop_back` fast path.
+pub struct Stealer<T> {
+    inner: Arc<WorkStealingInner<T>>,
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Create a bounded Chase-Lev work-stealing deque, returning the single
+/// `Owner` handle and a `Stealer` that can be cloned for as many thief
+/// threads as needed.
+pub fn work_stealing_deque<T>(capacity: usize) -> (Owner<T>, Stealer<T>) {
+    assert!(capacity > 0, "capacity must be non-zero");
+    let buffer = (0..capacity)
+        .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+        .collect();
+    let inner = Arc::new(WorkStealingInner {
+        buffer,
+        capacity,
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(TailCounter {
+            tail: AtomicUsize::new(0),
+            cached_head: UnsafeCell::new(0),
+        }),
+    });
+    (
+        Owner {
+            inner: inner.clone(),
+            _not_sync: PhantomData,
+        },
+        Stealer { inner },
+    )
+}
+
+impl<T> Owner<T> {
+    /// Push a value onto the tail end. Only ever called by the owning
+    /// thread, so it needs no CAS: just a capacity check and a release
+    /// store of the new `tail`. The capacity check first consults the
+    /// owner-local `cached_head` (same cache line as `tail`) and only
+    /// reads the real, cross-line `head` when the cache suggests the ring
+    /// might be full, so the common (not-full) case never touches the
+    /// stealers' cache line at all.
+    pub fn push_back(&self, value: T) -> Result<(), T> {
+        let tail = self.inner.tail.tail.load(Ordering::Relaxed);
+        let mut head = unsafe { *self.inner.tail.cached_head.get() };
+
+        if tail.wrapping_sub(head) >= self.inner.capacity {
+            // The cache says we might be full: refresh it from the real
+            // head before concluding there's genuinely no room.
+            head = self.inner.head.load(Ordering::Acquire);
+            unsafe {
+                *self.inner.tail.cached_head.get() = head;
+            }
+            if tail.wrapping_sub(head) >= self.inner.capacity {
+                return Err(value);
+            }
+        }
+
+        unsafe {
+            (*self.inner.buffer[tail % self.inner.capacity].get()).write(value);
+        }
+        self.inner.tail.tail.store(tail + 1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pop a value from the tail end. Only the owning thread ever calls
+    /// this. When more than one element is present this never contends
+    /// with stealers; when exactly one element remains, it races a
+    /// concurrent `steal()` for that last slot via a CAS on `head`.
+    pub fn pop_back(&self) -> Option<T> {
+        let tail = self.inner.tail.tail.load(Ordering::Relaxed);
+        if tail == 0 {
+            return None;
+        }
+        let new_tail = tail - 1;
+        self.inner.tail.tail.store(new_tail, Ordering::Relaxed);
+        // Make the tail decrement visible before reading head.
+        std::sync::atomic::fence(Ordering::SeqCst);
+        let head = self.inner.head.load(Ordering::Relaxed);
+        // We're already paying for the cross-line read above; keep the
+        // push-side cache fresh for free.
+        unsafe {
+            *self.inner.tail.cached_head.get() = head;
+        }
+
+        if head > new_tail {
+            // The deque was already empty; restore tail and bail out.
+            self.inner.tail.tail.store(tail, Ordering::Relaxed);
+            return None;
+        }
+
+        let value = unsafe {
+            (*self.inner.buffer[new_tail % self.inner.capacity].get()).assume_init_read()
+        };
+
+        if head == new_tail {
+            // Exactly one element was left: a stealer may be racing us for it.
+            if self
+                .inner
+                .head
+                .compare_exchange(head, head + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_err()
+            {
+                // Lost the race: a stealer already owns this value, so our
+                // local copy must not be dropped (no double free/drop of the
+                // same logical item).
+                std::mem::forget(value);
+                self.inner.tail.tail.store(tail, Ordering::Relaxed);
+                return None;
+            }
+            self.inner.tail.tail.store(tail, Ordering::Relaxed);
+        }
+
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        let tail = self.inner.tail.tail.load(Ordering::Relaxed);
+        tail.saturating_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Stealer<T> {
+    /// Take a value from the head end. Safe to call from any number of
+    /// threads concurrently (including concurrently with the owner's
+    /// `pop_back`); only one caller ever wins the slot.
+    pub fn steal(&self) -> Option<T> {
+        let head = self.inner.head.load(Ordering::Acquire);
+        let tail = self.inner.tail.tail.load(Ordering::Acquire);
+        if head >= tail {
+            return None;
+        }
+
+        let value =
+            unsafe { (*self.inner.buffer[head % self.inner.capacity].get()).assume_init_read() };
+
+        if self
+            .inner
+            .head
+            .compare_exchange(head, head + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            // Lost the race to another stealer or the owner; discard our copy.
+            std::mem::forget(value);
+            return None;
+        }
+
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        let head = self.inner.head.load(Ordering::Relaxed);
+        let tail = self.inner.tail.tail.load(Ordering::Relaxed);
+        tail.saturating_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Wakers shared between the async [`Sender`] and [`Receiver`] ends of a
+/// channel, plus a count of live senders so the receiver can tell a closed
+/// channel (no senders left) apart from a merely-empty one.
+struct AsyncWakers {
+    consumer: AtomicWaker,
+    producer: AtomicWaker,
+    senders: AtomicUsize,
+}
+
+/// The async-producer half of a [`channel`]. Implements `futures::Sink<T>`
+/// over the same lock-free ring used by [`LockFreeDeque`], so a full buffer
+/// parks the sending task instead of spinning.
+pub struct Sender<T> {
+    queue: Arc<LockFreeDeque<T>>,
+    wakers: Arc<AsyncWakers>,
+}
+
+/// The async-consumer half of a [`channel`]. Implements `futures::Stream<Item
+/// = T>` over the same lock-free ring used by [`LockFreeDeque`], so an empty
+/// buffer parks the receiving task instead of spinning.
+pub struct Receiver<T> {
+    queue: Arc<LockFreeDeque<T>>,
+    wakers: Arc<AsyncWakers>,
+}
+
+/// Create an async multi-producer, single-consumer channel backed by a
+/// [`LockFreeDeque`] of the given capacity. Any number of `Sender`s (via
+/// `Sender::clone`) may be created; there is one `Receiver`, which is not
+/// `Clone`. Once all senders have dropped, the `Receiver` drains whatever is left in
+/// the buffer and then yields `None`.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let queue = LockFreeDeque::with_capacity(capacity);
+    let wakers = Arc::new(AsyncWakers {
+        consumer: AtomicWaker::new(),
+        producer: AtomicWaker::new(),
+        senders: AtomicUsize::new(1),
+    });
+    (
+        Sender {
+            queue: queue.clone(),
+            wakers: wakers.clone(),
+        },
+        Receiver { queue, wakers },
+    )
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.wakers.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            queue: self.queue.clone(),
+            wakers: self.wakers.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.wakers.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // That was the last sender: wake the receiver so it notices the
+            // channel is closed and can drain the rest of the buffer.
+            self.wakers.consumer.wake();
+        }
+    }
+}
+
+impl<T> Sink<T> for Sender<T> {
+    type Error = T;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if !self.queue.is_full() {
+            return Poll::Ready(Ok(()));
+        }
+        self.wakers.producer.register(cx.waker());
+        if self.queue.is_full() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let result = self.queue.push_back(item);
+        if result.is_ok() {
+            self.wakers.consumer.wake();
+        }
+        result
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(value) = self.queue.pop_front() {
+            self.wakers.producer.wake();
+            return Poll::Ready(Some(value));
+        }
+
+        // Register before the second check so a push that races us between
+        // the first `pop_front` and here is never missed.
+        self.wakers.consumer.register(cx.waker());
+
+        // Read the sender count before the second pop: if it is already 0,
+        // every push happened before it, so an empty pop below really means
+        // the buffer is drained.
+        let closed = self.wakers.senders.load(Ordering::Acquire) == 0;
+
+        if let Some(value) = self.queue.pop_front() {
+            self.wakers.producer.wake();
+            return Poll::Ready(Some(value));
+        }
+
+        if closed {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Same Vyukov ring as [`LockFreeDeque`], except `enqueue_pos` and
+/// `dequeue_pos` are plain adjacent fields instead of [`CachePadded`].
+/// Exists only so `spsc_throughput_benchmark` has an apples-to-apples
+/// baseline to diff the padded ring against; see [`CachePadded`]'s doc
+/// comment for what the padding is meant to buy.
+struct UnpaddedRing<T> {
+    buffer: Vec<Cell<T>>,
+    capacity: usize,
+    enqueue_pos: AtomicUsize,
+    dequeue_pos: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for UnpaddedRing<T> {}
+unsafe impl<T: Send> Sync for UnpaddedRing<T> {}
+
+impl<T> UnpaddedRing<T> {
+    fn with_capacity(capacity: usize) -> Arc<Self> {
+        assert!(capacity >= 2, "capacity must be at least 2");
+        let buffer = (0..capacity)
+            .map(|i| Cell {
+                seq: AtomicUsize::new(i),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        Arc::new(Self {
+            buffer,
+            capacity,
+            enqueue_pos: AtomicUsize::new(0),
+            dequeue_pos: AtomicUsize::new(0),
+        })
+    }
+
+    fn push_back(&self, value: T) -> Result<(), T> {
+        let mut pos = self.enqueue_pos.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.buffer[pos % self.capacity];
+            let seq = cell.seq.load(Ordering::Acquire);
+            let diff = seq as isize - pos as isize;
+
+            if diff == 0 {
+                if self
+                    .enqueue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    unsafe {
+                        (*cell.data.get()).write(value);
+                    }
+                    cell.seq.store(pos + 1, Ordering::Release);
+                    return Ok(());
+                }
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                return Err(value);
+            } else {
+                pos = self.enqueue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn pop_front(&self) -> Option<T> {
+        let mut pos = self.dequeue_pos.load(Ordering::Relaxed);
+        loop {
+            let cell = &self.buffer[pos % self.capacity];
+            let seq = cell.seq.load(Ordering::Acquire);
+            let diff = seq as isize - (pos + 1) as isize;
+
+            if diff == 0 {
+                if self
+                    .dequeue_pos
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    let value = unsafe { (*cell.data.get()).assume_init_read() };
+                    cell.seq.store(pos + self.capacity, Ordering::Release);
+                    return Some(value);
+                }
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.dequeue_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for UnpaddedRing<T> {
+    fn drop(&mut self) {
+        let mut pos = *self.dequeue_pos.get_mut();
+        let end = *self.enqueue_pos.get_mut();
+        while pos != end {
+            let cell = &mut self.buffer[pos % self.capacity];
+            unsafe {
+                cell.data.get_mut().assume_init_drop();
+            }
+            pos = pos.wrapping_add(1);
+        }
+    }
+}
+
+/// Manual SPSC throughput benchmark (this crate has no `cargo bench`
+/// harness, so run it with `cargo run --release -- --bench`). Runs the
+/// same push/pop workload against the cache-padded ring and the
+/// [`UnpaddedRing`] baseline and reports both ops/sec numbers side by
+/// side, so the improvement the `enqueue_pos`/`dequeue_pos` padding buys
+/// is a number you can actually read off instead of an assertion.
+fn spsc_throughput_benchmark() {
+    use std::time::Instant;
+
+    const ITEMS: usize = 1_000_000;
+
+    fn run_padded(items: usize) -> f64 {
+        let deque = LockFreeDeque::with_capacity(1024);
+        let producer_deque = deque.clone();
+        let start = Instant::now();
+        let producer = thread::spawn(move || {
+            for i in 0..items {
+                while producer_deque.push_back(i).is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+        });
+
+        let mut received = 0usize;
+        while received < items {
+            if deque.pop_front().is_some() {
+                received += 1;
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+        producer.join().unwrap();
+        items as f64 / start.elapsed().as_secs_f64()
+    }
+
+    fn run_unpadded(items: usize) -> f64 {
+        let ring = UnpaddedRing::with_capacity(1024);
+        let producer_ring = ring.clone();
+        let start = Instant::now();
+        let producer = thread::spawn(move || {
+            for i in 0..items {
+                while producer_ring.push_back(i).is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+        });
+
+        let mut received = 0usize;
+        while received < items {
+            if ring.pop_front().is_some() {
+                received += 1;
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+        producer.join().unwrap();
+        items as f64 / start.elapsed().as_secs_f64()
+    }
+
+    let padded_ops_per_sec = run_padded(ITEMS);
+    let unpadded_ops_per_sec = run_unpadded(ITEMS);
+
+    println!(
+        "SPSC throughput: padded {:.0} ops/sec, unpadded {:.0} ops/sec ({:.2}x)",
+        padded_ops_per_sec,
+        unpadded_ops_per_sec,
+        padded_ops_per_sec / unpadded_ops_per_sec
+    );
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--bench") {
+        spsc_throughput_benchmark();
+        return;
+    }
+
+    println!("Running lock-free deque tests...");
+
+    // Run a simple demo
+    let deque = LockFreeDeque::with_capacity(5);
+
+    println!("Pushing values: 1, 2, 3");
+    deque.push_back(1).unwrap();
+    deque.push_back(2).unwrap();
+    deque.push_back(3).unwrap();
+
+    println!("Current length: {}", deque.len());
+
+    println!("Popping values:");
+    while let Some(value) = deque.pop_front() {
+        println!("  Popped: {}", value);
+    }
+
+    println!("Deque is empty: {}", deque.is_empty());
+    println!("All tests completed!");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_basic_operations() {
+        let deque = LockFreeDeque::with_capacity(4);
+
+        // Test empty state
+        assert!(deque.is_empty());
+        assert_eq!(deque.len(), 0);
+        assert_eq!(deque.pop_front(), None);
+
+        // Test push and pop
+        assert!(deque.push_back(1).is_ok());
+        assert_eq!(deque.len(), 1);
+        assert!(!deque.is_empty());
+
+        assert!(deque.push_back(2).is_ok());
+        assert_eq!(deque.len(), 2);
+
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.len(), 1);
+
+        assert_eq!(deque.pop_front(), Some(2));
+        assert!(deque.is_empty());
+        assert_eq!(deque.pop_front(), None);
+    }
+
+    #[test]
+    fn test_capacity_limits() {
+        let deque = LockFreeDeque::with_capacity(2);
+
+        // Fill the deque
+        assert!(deque.push_back(1).is_ok());
+        assert!(deque.push_back(2).is_ok());
+        assert!(deque.is_full());
+
+        // Try to push when full
+        let result = deque.push_back(3);
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), 3);
+
+        // Pop one and push again
+        assert_eq!(deque.pop_front(), Some(1));
+        assert!(deque.push_back(3).is_ok());
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), Some(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 2")]
+    fn test_capacity_below_minimum_is_rejected() {
+        // Capacity 1 is the degenerate case the per-slot sequence scheme
+        // can't support (see the comment on the assert in with_capacity),
+        // so the constructor must reject it rather than let callers hit
+        // the hang/corruption it used to cause.
+        LockFreeDeque::<i32>::with_capacity(1);
+    }
+
+    #[test]
+    fn test_ring_buffer_wraparound() {
+        let deque = LockFreeDeque::with_capacity(3);
+
+        // Fill and empty multiple times to test wraparound
+        for i in 0..10 {
+            assert!(deque.push_back(i).is_ok());
+            assert!(deque.push_back(i + 1).is_ok());
+            assert!(deque.push_back(i + 2).is_ok());
+
+            assert_eq!(deque.pop_front(), Some(i));
+            assert_eq!(deque.pop_front(), Some(i + 1));
+            assert_eq!(deque.pop_front(), Some(i + 2));
+        }
+    }
+
+    #[test]
+    fn test_single_producer_multiple_consumers() {
+        let deque = LockFreeDeque::with_capacity(100);
+        let producer_deque = deque.clone();
+        let consumer_deque = deque.clone();
+
+        // Producer thread
+        let producer = thread::spawn(move || {
+            for i in 0..50 {
+                while producer_deque.push_back(i).is_err() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+
+        // Consumer threads
+        let mut consumers = Vec::new();
+        for _ in 0..5 {
+            let consumer_deque = consumer_deque.clone();
+            let consumer = thread::spawn(move || {
+                let mut received = Vec::new();
+                while received.len() < 10 {
+                    if let Some(value) = consumer_deque.pop_front() {
+                        received.push(value);
+                    } else {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                received
+            });
+            consumers.push(consumer);
+        }
+
+        // Wait for producer to finish
+        producer.join().unwrap();
+
+        // Wait for all consumers and collect results
+        let mut all_received = Vec::new();
+        for consumer in consumers {
+            all_received.extend(consumer.join().unwrap());
+        }
+
+        // Verify we got all values (order doesn't matter due to multiple consumers)
+        all_received.sort();
+        let expected: Vec<i32> = (0..50).collect();
+        assert_eq!(all_received, expected);
+    }
+
+    #[test]
+    fn test_concurrent_producer_consumer() {
+        let deque = LockFreeDeque::with_capacity(10);
+        let producer_deque = deque.clone();
+        let consumer_deque = deque.clone();
+
+        // Producer thread
+        let producer = thread::spawn(move || {
+            for i in 0..100 {
+                while producer_deque.push_back(i).is_err() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+
+        // Consumer thread
+        let consumer = thread::spawn(move || {
+            let mut received = Vec::new();
+            while received.len() < 100 {
+                if let Some(value) = consumer_deque.pop_front() {
+                    received.push(value);
+                } else {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            received
+        });
+
+        // Wait for consumer to finish first (it will collect all 100 items)
+        let received = consumer.join().unwrap();
+
+        // Then wait for producer to finish
+        producer.join().unwrap();
+
+        // Verify we got all values
+        assert_eq!(received.len(), 100);
+        let mut sorted = received.clone();
+        sorted.sort();
+        let expected: Vec<i32> = (0..100).collect();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn test_string_values() {
+        let deque = LockFreeDeque::with_capacity(5);
+
+        assert!(deque.push_back("hello".to_string()).is_ok());
+        assert!(deque.push_back("world".to_string()).is_ok());
+
+        assert_eq!(deque.pop_front(), Some("hello".to_string()));
+        assert_eq!(deque.pop_front(), Some("world".to_string()));
+        assert_eq!(deque.pop_front(), None);
+    }
+
+    #[test]
+    fn test_stress_concurrent_access() {
+        let deque = LockFreeDeque::with_capacity(50);
+        let num_threads = 2;
+        let operations_per_thread = 50;
+
+        let mut producer_handles = Vec::new();
+        let mut consumer_handles = Vec::new();
+
+        // Create producer threads
+        for thread_id in 0..num_threads / 2 {
+            let deque = deque.clone();
+            let handle = thread::spawn(move || {
+                for i in 0..operations_per_thread {
+                    let value = (thread_id * operations_per_thread + i) as i32;
+                    while deque.push_back(value).is_err() {
+                        thread::sleep(Duration::from_nanos(1));
+                    }
+                }
+            });
+            producer_handles.push(handle);
+        }
+
+        // Create consumer threads
+        for _ in 0..num_threads / 2 {
+            let deque = deque.clone();
+            let handle = thread::spawn(move || {
+                let mut received = Vec::new();
+                while received.len() < operations_per_thread {
+                    if let Some(value) = deque.pop_front() {
+                        received.push(value);
+                    } else {
+                        thread::sleep(Duration::from_nanos(1));
+                    }
+                }
+                received
+            });
+            consumer_handles.push(handle);
+        }
+
+        // Wait for all producers
+        for handle in producer_handles {
+            let _ = handle.join();
+        }
+
+        // Wait for all consumers and collect results
+        let mut all_received = Vec::new();
+        for handle in consumer_handles {
+            if let Ok(received) = handle.join() {
+                all_received.extend(received);
+            }
+        }
+
+        // Verify we got all expected values
+        assert_eq!(all_received.len(), (num_threads / 2) * operations_per_thread);
+        all_received.sort();
+        let expected: Vec<i32> = (0..((num_threads / 2) * operations_per_thread)).map(|x| x as i32).collect();
+        assert_eq!(all_received, expected);
+
+        // Verify deque is empty
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_seq_counters_survive_many_wraps() {
+        // Regression test for the old tail-head overflow hazard: run far
+        // more push/pop laps than the capacity so every slot's `seq` wraps
+        // around several times.
+        let deque = LockFreeDeque::with_capacity(4);
+        for i in 0..10_000 {
+            assert!(deque.push_back(i).is_ok());
+            assert_eq!(deque.pop_front(), Some(i));
+        }
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_push_pop_at_minimum_capacity() {
+        // Regression test for a degenerate case in the per-slot sequence
+        // scheme: at capacity 1 a slot's `seq` can't distinguish "just
+        // written, not yet read" from "read and ready for the next write",
+        // so a second concurrent push_back would silently overwrite an
+        // unread item and pop_front could spin forever waiting for a slot
+        // that will never become ready. Capacity 2 is the smallest size the
+        // algorithm actually supports; exercise concurrent push/pop right
+        // at that boundary so a regression here hangs this test instead of
+        // silently corrupting data.
+        let deque = LockFreeDeque::with_capacity(2);
+        let producer_deque = deque.clone();
+
+        let producer = thread::spawn(move || {
+            for i in 0..10_000 {
+                while producer_deque.push_back(i).is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+        });
+
+        let mut received = Vec::with_capacity(10_000);
+        while received.len() < 10_000 {
+            if let Some(value) = deque.pop_front() {
+                received.push(value);
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(received, (0..10_000).collect::<Vec<_>>());
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_work_stealing_owner_only() {
+        let (owner, _stealer) = work_stealing_deque::<i32>(4);
+
+        assert!(owner.is_empty());
+        assert!(owner.push_back(1).is_ok());
+        assert!(owner.push_back(2).is_ok());
+        assert!(owner.push_back(3).is_ok());
+        assert_eq!(owner.len(), 3);
+
+        // LIFO from the owner's end.
+        assert_eq!(owner.pop_back(), Some(3));
+        assert_eq!(owner.pop_back(), Some(2));
+        assert_eq!(owner.pop_back(), Some(1));
+        assert_eq!(owner.pop_back(), None);
+    }
+
+    #[test]
+    fn test_work_stealing_steal_is_fifo_from_head() {
+        let (owner, stealer) = work_stealing_deque::<i32>(4);
+        owner.push_back(1).unwrap();
+        owner.push_back(2).unwrap();
+        owner.push_back(3).unwrap();
+
+        assert_eq!(stealer.steal(), Some(1));
+        assert_eq!(stealer.steal(), Some(2));
+        assert_eq!(owner.pop_back(), Some(3));
+        assert_eq!(stealer.steal(), None);
+    }
+
+    #[test]
+    fn test_work_stealing_concurrent_thieves() {
+        let (owner, stealer) = work_stealing_deque::<i32>(256);
+        for i in 0..200 {
+            owner.push_back(i).unwrap();
+        }
+
+        let mut thieves = Vec::new();
+        for _ in 0..4 {
+            let stealer = stealer.clone();
+            thieves.push(thread::spawn(move || {
+                let mut stolen = Vec::new();
+                loop {
+                    match stealer.steal() {
+                        Some(value) => stolen.push(value),
+                        None if stealer.is_empty() => break,
+                        None => thread::sleep(Duration::from_micros(50)),
+                    }
+                }
+                stolen
+            }));
+        }
+
+        let mut all_values = Vec::new();
+        while let Some(value) = owner.pop_back() {
+            all_values.push(value);
+        }
+        for thief in thieves {
+            all_values.extend(thief.join().unwrap());
+        }
+
+        all_values.sort();
+        let expected: Vec<i32> = (0..200).collect();
+        assert_eq!(all_values, expected);
+    }
+
+    #[test]
+    fn test_async_channel_send_recv() {
+        use futures::executor::block_on;
+        use futures::{SinkExt, StreamExt};
+
+        let (mut tx, mut rx) = channel::<i32>(4);
+
+        block_on(async {
+            tx.send(1).await.unwrap();
+            tx.send(2).await.unwrap();
+            assert_eq!(rx.next().await, Some(1));
+            assert_eq!(rx.next().await, Some(2));
+        });
+    }
+
+    #[test]
+    fn test_async_channel_closes_after_drain() {
+        use futures::executor::block_on;
+        use futures::{SinkExt, StreamExt};
+
+        let (mut tx, mut rx) = channel::<i32>(4);
+
+        block_on(async {
+            tx.send(1).await.unwrap();
+            tx.send(2).await.unwrap();
+            drop(tx);
+
+            // Dropping the last sender must not lose what's already buffered.
+            assert_eq!(rx.next().await, Some(1));
+            assert_eq!(rx.next().await, Some(2));
+            assert_eq!(rx.next().await, None);
+        });
+    }
+
+    #[test]
+    fn test_async_channel_wakes_across_threads() {
+        use futures::executor::block_on;
+        use futures::{SinkExt, StreamExt};
+
+        let (mut tx, mut rx) = channel::<i32>(2);
+
+        let producer = thread::spawn(move || {
+            block_on(async {
+                for i in 0..20 {
+                    tx.send(i).await.unwrap();
+                }
+            });
+        });
+
+        let received = block_on(async {
+            let mut received = Vec::new();
+            while received.len() < 20 {
+                if let Some(value) = rx.next().await {
+                    received.push(value);
+                }
+            }
+            received
+        });
+
+        producer.join().unwrap();
+        assert_eq!(received, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_send_blocking_unparks_on_pop() {
+        let deque = LockFreeDeque::with_capacity(2);
+        deque.push_back(1).unwrap();
+        deque.push_back(2).unwrap();
+        assert!(deque.is_full());
+
+        let producer_deque = deque.clone();
+        let producer = thread::spawn(move || {
+            producer_deque.send_blocking(3);
+        });
+
+        // Give the producer a moment to park on the full ring.
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(deque.pop_front(), Some(1));
+
+        producer.join().unwrap();
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), Some(3));
+    }
+
+    #[test]
+    fn test_recv_blocking_unparks_on_push() {
+        let deque = LockFreeDeque::with_capacity(4);
+        let consumer_deque = deque.clone();
+
+        let consumer = thread::spawn(move || consumer_deque.recv_blocking());
+
+        thread::sleep(Duration::from_millis(20));
+        deque.push_back(42).unwrap();
+
+        assert_eq!(consumer.join().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_recv_timeout_expires_when_empty() {
+        let deque: Arc<LockFreeDeque<i32>> = LockFreeDeque::with_capacity(4);
+        let start = Instant::now();
+        assert_eq!(deque.recv_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_recv_timeout_returns_value_before_deadline() {
+        let deque = LockFreeDeque::with_capacity(4);
+        deque.push_back(7).unwrap();
+        assert_eq!(deque.recv_timeout(Duration::from_secs(1)), Some(7));
+    }
+
+    #[test]
+    fn test_select_returns_ready_queue() {
+        let a = LockFreeDeque::with_capacity(4);
+        let b = LockFreeDeque::with_capacity(4);
+        b.push_back("from b").unwrap();
+
+        let (index, value) = select(&[&a, &b]);
+        assert_eq!(index, 1);
+        assert_eq!(value, "from b");
+    }
+
+    #[test]
+    fn test_select_blocks_until_one_queue_is_ready() {
+        let a: Arc<LockFreeDeque<i32>> = LockFreeDeque::with_capacity(4);
+        let b: Arc<LockFreeDeque<i32>> = LockFreeDeque::with_capacity(4);
+
+        let feeder_b = b.clone();
+        let feeder = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            feeder_b.push_back(99).unwrap();
+        });
+
+        let (index, value) = select(&[&a, &b]);
+        assert_eq!(index, 1);
+        assert_eq!(value, 99);
+
+        feeder.join().unwrap();
+    }
+
+    #[test]
+    fn test_select_does_not_leak_registrations_on_a_quiet_queue() {
+        // Regression test: select used to register the calling thread into
+        // every watched queue's WaitList on every unready pass and never
+        // deregister, so a quiet queue sitting alongside a busy one would
+        // accumulate one stale Thread per call forever.
+        let busy: Arc<LockFreeDeque<i32>> = LockFreeDeque::with_capacity(4);
+        let quiet: Arc<LockFreeDeque<i32>> = LockFreeDeque::with_capacity(4);
+
+        for i in 0..50 {
+            // Push from another thread after a short delay so select first
+            // finds both queues empty and has to register and park.
+            let feeder_busy = busy.clone();
+            let feeder = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(1));
+                feeder_busy.push_back(i).unwrap();
+            });
+            let (index, value) = select(&[&busy, &quiet]);
+            assert_eq!(index, 0);
+            assert_eq!(value, i);
+            feeder.join().unwrap();
+        }
+
+        assert_eq!(quiet.recv_waiters.count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_unbounded_queue_basic_operations() {
+        let queue = UnboundedQueue::new();
+
+        assert_eq!(queue.pop_front(), None);
+
+        queue.push_back(1);
+        queue.push_back(2);
+        queue.push_back(3);
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert_eq!(queue.pop_front(), Some(2));
+        assert_eq!(queue.pop_front(), Some(3));
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn test_unbounded_queue_never_rejects_a_push() {
+        // Far more items than any LockFreeDeque capacity used elsewhere in
+        // this file would allow without blocking or erroring.
+        let queue = UnboundedQueue::new();
+        for i in 0..100_000 {
+            queue.push_back(i);
+        }
+        for i in 0..100_000 {
+            assert_eq!(queue.pop_front(), Some(i));
+        }
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn test_unbounded_queue_drops_pending_values() {
+        use std::sync::atomic::AtomicUsize as Counter;
+
+        struct DropCounter(Arc<Counter>);
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let drops = Arc::new(Counter::new(0));
+        {
+            let queue = UnboundedQueue::new();
+            queue.push_back(DropCounter(drops.clone()));
+            queue.push_back(DropCounter(drops.clone()));
+            queue.push_back(DropCounter(drops.clone()));
+            assert!(queue.pop_front().is_some());
+            // Two values are still sitting in the queue when it's dropped.
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_unbounded_queue_concurrent_producers_consumers() {
+        let queue = UnboundedQueue::new();
+        let num_producers: usize = 4;
+        let items_per_producer: usize = 2_000;
+
+        let mut producers = Vec::new();
+        for p in 0..num_producers {
+            let queue = queue.clone();
+            producers.push(thread::spawn(move || {
+                for i in 0..items_per_producer {
+                    queue.push_back(p * items_per_producer + i);
+                }
+            }));
+        }
+
+        let total = num_producers * items_per_producer;
+        let mut consumers = Vec::new();
+        for _ in 0..num_producers {
+            let queue = queue.clone();
+            consumers.push(thread::spawn(move || {
+                let mut received = Vec::new();
+                while received.len() < total / num_producers {
+                    if let Some(value) = queue.pop_front() {
+                        received.push(value);
+                    } else {
+                        thread::sleep(Duration::from_micros(50));
+                    }
+                }
+                received
+            }));
+        }
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let mut all_received = Vec::new();
+        for consumer in consumers {
+            all_received.extend(consumer.join().unwrap());
+        }
+
+        all_received.sort();
+        let expected: Vec<usize> = (0..total).collect();
+        assert_eq!(all_received, expected);
+    }
+
+    #[test]
+    fn test_queue_trait_is_generic_over_bounded_and_unbounded() {
+        fn drain_via_trait<T>(queue: &dyn Queue<T>) -> Vec<T> {
+            let mut values = Vec::new();
+            while let Some(value) = queue.pop() {
+                values.push(value);
+            }
+            values
+        }
+
+        let bounded = LockFreeDeque::with_capacity(4);
+        bounded.push(1).unwrap();
+        bounded.push(2).unwrap();
+        assert_eq!(drain_via_trait(&*bounded), vec![1, 2]);
+
+        let unbounded = UnboundedQueue::new();
+        unbounded.push(1).unwrap();
+        unbounded.push(2).unwrap();
+        assert_eq!(drain_via_trait(&*unbounded), vec![1, 2]);
+    }
+}